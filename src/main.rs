@@ -1,30 +1,244 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 enum ParseError {
-    #[error("expected {expected:?}, found {found:?}")]
-    Expected { expected: String, found: Token },
+    #[error("expected one of {}, found {found}", format_kinds(expected))]
+    Expected {
+        expected: Vec<TokenKind>,
+        found: Token,
+        span: usize,
+    },
     #[error("unexpected end of input")]
-    UnexpectedEndOfInput,
+    UnexpectedEndOfInput { span: usize },
     #[error("invalid token {0:?}")]
-    InvalidToken(char),
+    InvalidToken(char, usize),
+    #[error("unexpected trailing token {found}")]
+    TrailingToken { found: Token, span: usize },
+    #[error("number {value} out of range for a 32-bit value")]
+    NumberOutOfRange { value: i64, span: usize },
+    #[error("number literal {text} out of range for a 64-bit value")]
+    NumberLiteralOverflow { text: String, span: usize },
 }
 
-#[derive(Debug, Copy, Clone)]
+// renders an expected-token set for `ParseError::Expected`'s message, e.g.
+// "`)`, number, `+`, `-`".
+fn format_kinds(kinds: &[TokenKind]) -> String {
+    kinds
+        .iter()
+        .map(TokenKind::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// merges, sorts, and dedups the expected-token sets gathered at a choice
+// point, so e.g. a bad prefix token reports "expected one of `(`, number, identifier".
+fn expected(mut kinds: Vec<TokenKind>, found: Token, span: usize) -> ParseError {
+    kinds.sort();
+    kinds.dedup();
+    ParseError::Expected {
+        expected: kinds,
+        found,
+        span,
+    }
+}
+
+#[derive(Debug, Clone)]
 enum Token {
     LParen,
     RParen,
-    Digit(u32),
+    Number(i64),
+    Identifier(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Equals,
+}
+
+// the shape of a token, without its payload - what `expected()` collects and compares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum TokenKind {
+    LParen,
+    RParen,
+    Number,
+    Identifier,
     Plus,
     Minus,
+    Star,
+    Slash,
+    Equals,
+}
+
+impl Token {
+    fn kind(&self) -> TokenKind {
+        match self {
+            Token::LParen => TokenKind::LParen,
+            Token::RParen => TokenKind::RParen,
+            Token::Number(_) => TokenKind::Number,
+            Token::Identifier(_) => TokenKind::Identifier,
+            Token::Plus => TokenKind::Plus,
+            Token::Minus => TokenKind::Minus,
+            Token::Star => TokenKind::Star,
+            Token::Slash => TokenKind::Slash,
+            Token::Equals => TokenKind::Equals,
+        }
+    }
+}
+
+// renders the surface symbol a token was lexed from, for user-facing
+// error text - `{token:?}` would otherwise leak variants like
+// `Identifier("x")` or `Star` straight from `derive(Debug)`.
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(n) => write!(f, "`{n}`"),
+            Token::Identifier(name) => write!(f, "`{name}`"),
+            _ => write!(f, "{}", self.kind()),
+        }
+    }
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            TokenKind::LParen => "`(`",
+            TokenKind::RParen => "`)`",
+            TokenKind::Number => "number",
+            TokenKind::Identifier => "identifier",
+            TokenKind::Plus => "`+`",
+            TokenKind::Minus => "`-`",
+            TokenKind::Star => "`*`",
+            TokenKind::Slash => "`/`",
+            TokenKind::Equals => "`=`",
+        };
+        write!(f, "{symbol}")
+    }
+}
+
+// variable bindings made by `let` statements, consulted when an `Expr::Var` is evaluated.
+type Environment = HashMap<String, i32>;
+
+// a token paired with the char offset into the original input where it starts.
+#[derive(Debug, Clone)]
+struct Spanned<T> {
+    value: T,
+    span: usize,
+}
+
+// streams `Spanned<Token>`s out of the input one lexeme at a time, so a
+// multi-digit number or multi-letter identifier is produced as a single
+// token instead of being reassembled by the parser.
+struct Tokenizer<'a> {
+    chars: Peekable<Chars<'a>>,
+    pos: usize,
+}
+
+impl<'a> Tokenizer<'a> {
+    fn new(input: &'a str) -> Self {
+        Tokenizer {
+            chars: input.chars().peekable(),
+            pos: 0,
+        }
+    }
+
+    fn advance(&mut self) -> Option<char> {
+        let c = self.chars.next();
+        if c.is_some() {
+            self.pos += 1;
+        }
+        c
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Spanned<Token>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while matches!(self.chars.peek(), Some(c) if c.is_whitespace()) {
+            self.advance();
+        }
+
+        let start = self.pos;
+        let c = *self.chars.peek()?;
+
+        let value = match c {
+            '(' => {
+                self.advance();
+                Token::LParen
+            }
+            ')' => {
+                self.advance();
+                Token::RParen
+            }
+            '+' => {
+                self.advance();
+                Token::Plus
+            }
+            '-' => {
+                self.advance();
+                Token::Minus
+            }
+            '*' => {
+                self.advance();
+                Token::Star
+            }
+            '/' => {
+                self.advance();
+                Token::Slash
+            }
+            '=' => {
+                self.advance();
+                Token::Equals
+            }
+            c if c.is_ascii_digit() => {
+                let mut number = 0i64;
+                let mut text = String::new();
+                let mut overflowed = false;
+                while let Some(digit) = self.chars.peek().and_then(|c| c.to_digit(10)) {
+                    text.push(char::from_digit(digit, 10).unwrap());
+                    number = match number
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(digit as i64))
+                    {
+                        Some(n) => n,
+                        None => {
+                            overflowed = true;
+                            number
+                        }
+                    };
+                    self.advance();
+                }
+                if overflowed {
+                    return Some(Err(ParseError::NumberLiteralOverflow { text, span: start }));
+                }
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() => {
+                let mut name = String::new();
+                while matches!(self.chars.peek(), Some(c) if c.is_alphabetic()) {
+                    name.push(self.advance().unwrap());
+                }
+                Token::Identifier(name)
+            }
+            _ => {
+                self.advance();
+                return Some(Err(ParseError::InvalidToken(c, start)));
+            }
+        };
+
+        Some(Ok(Spanned { value, span: start }))
+    }
 }
 
 /* grammars
 
-expr   = term { ("+" | "-"), term };
+expr   = term { ("+" | "-" | "*" | "/"), term };  -- precedence climbed via binding powers, see parse_expr
 term   = "(", expr, ")" | number;
-number = digit, { digit };
-digit  = "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9"
+number = Token::Number;  -- lexed whole by the Tokenizer, not reassembled digit-by-digit
 
 */
 
@@ -32,155 +246,450 @@ digit  = "0" | "1" | "2" | "3" | "4" | "5" | "6" | "7" | "8" | "9"
 enum Expr {
     Add(Box<Expr>, Box<Expr>),
     Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
     Number(i32),
+    Var(String),
 }
 
-fn parse_expr(tokens: &[Token]) -> Result<(Expr, &[Token]), ParseError> {
-    let (term, remaining) = parse_term(tokens)?;
+// a single line of input: either a bare expression or a `let` binding.
+#[derive(Debug)]
+enum Stmt {
+    Let(String, Expr),
+    Expr(Expr),
+}
 
-    match remaining.first() {
-        // addition
-        Some(Token::Plus) => {
-            let (other, remaining) = parse_term(&remaining[1..])?;
-            Ok((Expr::Add(Box::new(term), Box::new(other)), remaining))
-        }
+// an infix operator's left/right binding power and the `Expr` variant it builds.
+type BinOp = (u8, u8, fn(Box<Expr>, Box<Expr>) -> Expr);
 
-        // subtraction
-        Some(Token::Minus) => {
-            let (other, remaining) = parse_expr(&remaining[1..])?;
-            Ok((Expr::Sub(Box::new(term), Box::new(other)), remaining))
-        }
+// precedence-climbing (Pratt) parser: parse a prefix operand via `parse_term`,
+// then repeatedly fold in infix operators whose binding power clears `min_bp`.
+// `eof` is the span to blame when the input runs out mid-expression.
+fn parse_expr(
+    tokens: &[Spanned<Token>],
+    min_bp: u8,
+    eof: usize,
+) -> Result<(Expr, &[Spanned<Token>]), ParseError> {
+    let (mut lhs, mut remaining) = parse_term(tokens, eof)?;
 
-        // only a term
-        Some(_) => Ok((term, remaining)),
+    loop {
+        let (left_bp, right_bp, build): BinOp = match remaining.first().map(|t| &t.value) {
+            Some(Token::Plus) => (1, 2, Expr::Add),
+            Some(Token::Minus) => (1, 2, Expr::Sub),
+            Some(Token::Star) => (3, 4, Expr::Mul),
+            Some(Token::Slash) => (3, 4, Expr::Div),
+            _ => break,
+        };
+
+        if left_bp < min_bp {
+            break;
+        }
 
-        // done parsing
-        None => Ok((term, remaining)),
+        let (rhs, rest) = parse_expr(&remaining[1..], right_bp, eof)?;
+        lhs = build(Box::new(lhs), Box::new(rhs));
+        remaining = rest;
     }
+
+    Ok((lhs, remaining))
 }
 
-fn parse_term(tokens: &[Token]) -> Result<(Expr, &[Token]), ParseError> {
+fn parse_term(
+    tokens: &[Spanned<Token>],
+    eof: usize,
+) -> Result<(Expr, &[Spanned<Token>]), ParseError> {
     match tokens.first() {
         // parenthesis
-        Some(Token::LParen) => {
-            let (expr, remaining) = parse_expr(&tokens[1..])?;
+        Some(t) if matches!(t.value, Token::LParen) => {
+            let (expr, remaining) = parse_expr(&tokens[1..], 0, eof)?;
 
             match remaining.first() {
-                Some(Token::RParen) => Ok((expr, &remaining[1..])),
-                Some(token) => Err(ParseError::Expected {
-                    expected: "right parenthesis".to_string(),
-                    found: *token,
-                }),
-                None => Err(ParseError::UnexpectedEndOfInput),
+                Some(t) if matches!(t.value, Token::RParen) => Ok((expr, &remaining[1..])),
+                Some(t) => Err(expected(vec![TokenKind::RParen], t.value.clone(), t.span)),
+                None => Err(ParseError::UnexpectedEndOfInput { span: eof }),
             }
         }
-        Some(_) => parse_number(tokens),
-        None => Err(ParseError::UnexpectedEndOfInput),
+        // variable reference
+        Some(t) if matches!(t.value, Token::Identifier(_)) => {
+            let name = match &t.value {
+                Token::Identifier(name) => name.clone(),
+                _ => unreachable!(),
+            };
+            Ok((Expr::Var(name), &tokens[1..]))
+        }
+        Some(t) if matches!(t.value, Token::Number(_)) => parse_number(tokens, eof),
+        Some(t) => Err(expected(
+            vec![TokenKind::LParen, TokenKind::Number, TokenKind::Identifier],
+            t.value.clone(),
+            t.span,
+        )),
+        None => Err(ParseError::UnexpectedEndOfInput { span: eof }),
     }
 }
 
-fn parse_number(tokens: &[Token]) -> Result<(Expr, &[Token]), ParseError> {
+fn parse_number(
+    tokens: &[Spanned<Token>],
+    eof: usize,
+) -> Result<(Expr, &[Spanned<Token>]), ParseError> {
     match tokens.first() {
-        Some(Token::Digit(_)) => {
-            let mut num_digits = 1;
-
-            let number = tokens
-                .iter()
-                .take_while(|&token| match token {
-                    Token::Digit(_) => true,
-                    _ => false,
-                })
-                .map(|token| match token {
-                    Token::Digit(d) => *d,
-                    _ => unreachable!(),
-                })
-                .reduce(|total, digit| {
-                    num_digits += 1;
-                    total * 10 + digit
-                })
-                .unwrap();
-
-            Ok((Expr::Number(number as i32), &tokens[num_digits..]))
-        }
-
-        // bad token
-        Some(token) => Err(ParseError::Expected {
-            expected: "digit".to_string(),
-            found: *token,
-        }),
+        Some(t) => match t.value {
+            Token::Number(n) => {
+                let n = i32::try_from(n).map_err(|_| ParseError::NumberOutOfRange {
+                    value: n,
+                    span: t.span,
+                })?;
+                Ok((Expr::Number(n), &tokens[1..]))
+            }
+            _ => Err(expected(vec![TokenKind::Number], t.value.clone(), t.span)),
+        },
 
         // end of line
-        None => Err(ParseError::UnexpectedEndOfInput),
+        None => Err(ParseError::UnexpectedEndOfInput { span: eof }),
     }
 }
 
-fn tokenize(input: &String) -> Result<Vec<Token>, ParseError> {
-    input
-        .chars()
-        .enumerate()
-        .filter(|(_, c)| !c.is_whitespace())
-        .map(|(_, c)| match c {
-            '(' => Ok(Token::LParen),
-            ')' => Ok(Token::RParen),
-            '+' => Ok(Token::Plus),
-            '-' => Ok(Token::Minus),
-            _ => Ok(Token::Digit(
-                c.to_digit(10).ok_or(ParseError::InvalidToken(c))?,
-            )),
-        })
-        .collect::<Result<Vec<Token>, ParseError>>()
+fn tokenize(input: &str) -> Result<Vec<Spanned<Token>>, ParseError> {
+    Tokenizer::new(input).collect()
 }
 
-fn parse(input: &String) -> Result<Expr, ParseError> {
+// whether a token stream opens with the `let` keyword - the one point
+// where the grammar branches into the statement form `parse_let` handles.
+fn is_let_start(tokens: &[Spanned<Token>]) -> bool {
+    matches!(tokens.first(), Some(t) if matches!(&t.value, Token::Identifier(name) if name == "let"))
+}
+
+fn parse(input: &str) -> Result<Stmt, ParseError> {
+    let eof = input.chars().count();
     let tokens = tokenize(input)?;
-    let (expr, remaining) = parse_expr(&tokens)?;
+
+    if is_let_start(&tokens) {
+        return parse_let(&tokens[1..], eof);
+    }
+
+    let (expr, remaining) = parse_expr(&tokens, 0, eof)?;
+
+    match remaining.first() {
+        Some(t) => Err(ParseError::TrailingToken {
+            found: t.value.clone(),
+            span: t.span,
+        }),
+        None => Ok(Stmt::Expr(expr)),
+    }
+}
+
+fn parse_let(tokens: &[Spanned<Token>], eof: usize) -> Result<Stmt, ParseError> {
+    let (name, remaining) = match tokens.first() {
+        Some(t) => match &t.value {
+            Token::Identifier(name) => (name.clone(), &tokens[1..]),
+            _ => {
+                return Err(expected(
+                    vec![TokenKind::Identifier],
+                    t.value.clone(),
+                    t.span,
+                ))
+            }
+        },
+        None => return Err(ParseError::UnexpectedEndOfInput { span: eof }),
+    };
+
+    let remaining = match remaining.first() {
+        Some(t) if matches!(t.value, Token::Equals) => &remaining[1..],
+        Some(t) => return Err(expected(vec![TokenKind::Equals], t.value.clone(), t.span)),
+        None => return Err(ParseError::UnexpectedEndOfInput { span: eof }),
+    };
+
+    let (expr, remaining) = parse_expr(remaining, 0, eof)?;
 
     match remaining.first() {
-        Some(token) => Err(ParseError::Expected {
-            expected: "end of input".to_string(),
-            found: *token,
+        Some(t) => Err(ParseError::TrailingToken {
+            found: t.value.clone(),
+            span: t.span,
         }),
-        None => Ok(expr),
+        None => Ok(Stmt::Let(name, expr)),
+    }
+}
+
+// whether a token can legally start a term - the only place a fresh
+// `parse_expr` attempt can usefully resume after an error, since restarting
+// right on an operator would just fail the exact same way again.
+fn starts_term(kind: TokenKind) -> bool {
+    matches!(
+        kind,
+        TokenKind::LParen | TokenKind::Number | TokenKind::Identifier
+    )
+}
+
+// skips past the token that just failed, and any garbage after it, landing
+// on the next token that can start a term (or the end of input). Always
+// advances at least one token, so recovery can't stop on the very token
+// it was already given without making progress past it.
+fn synchronize(tokens: &[Spanned<Token>]) -> &[Spanned<Token>] {
+    let mut rest = match tokens.split_first() {
+        Some((_, rest)) => rest,
+        None => return tokens,
+    };
+
+    while let Some(t) = rest.first() {
+        if starts_term(t.value.kind()) {
+            break;
+        }
+        rest = &rest[1..];
+    }
+
+    rest
+}
+
+// parses as much of `input` as possible, using panic-mode recovery to skip
+// past a bad token and keep going, so one run surfaces every parse error
+// instead of stopping at the first one.
+fn collect_parse_errors(input: &str) -> Vec<ParseError> {
+    let eof = input.chars().count();
+
+    // drive the Tokenizer ourselves instead of going through `tokenize`,
+    // which stops at the first lex error - here we want
+    // every lex error, not just the first, alongside the parse errors below.
+    let mut tokens = Vec::new();
+    let mut errors = Vec::new();
+    for result in Tokenizer::new(input) {
+        match result {
+            Ok(token) => tokens.push(token),
+            Err(err) => errors.push(err),
+        }
     }
+
+    let mut remaining: &[Spanned<Token>] = &tokens;
+
+    while !remaining.is_empty() {
+        match parse_expr(remaining, 0, eof) {
+            // a full expression parsed, but tokens are left over - the same
+            // "unexpected trailing token" case `parse`/`parse_let` reject.
+            Ok((_, rest)) => match rest.first() {
+                Some(t) => {
+                    errors.push(ParseError::TrailingToken {
+                        found: t.value.clone(),
+                        span: t.span,
+                    });
+                    remaining = synchronize(rest);
+                }
+                None => remaining = rest,
+            },
+            Err(err) => {
+                // `err` can come from arbitrarily deep inside a nested
+                // `(...)` or the RHS of an operator, so resync from the
+                // token it actually blames, not from the top of this
+                // iteration's `remaining` - otherwise we just re-parse the
+                // same failure and report it over and over.
+                let span = error_span(&err);
+                let fail_at = remaining
+                    .iter()
+                    .position(|t| t.span == span)
+                    .unwrap_or(remaining.len());
+                errors.push(err);
+                remaining = synchronize(&remaining[fail_at..]);
+            }
+        }
+    }
+
+    // report in source order regardless of whether an error came from
+    // lexing (collected up front) or from the parse loop above.
+    errors.sort_by_key(error_span);
+    errors
 }
 
-fn evaluate(expr: &Expr) -> i32 {
+fn evaluate(expr: &Expr, env: &Environment, trace: bool) -> Result<i32, EvalError> {
     match expr {
         Expr::Add(lhs, rhs) => {
-            dbg!(expr);
-            let lhs = evaluate(lhs);
-            let rhs = evaluate(rhs);
+            if trace {
+                dbg!(expr);
+            }
+            let lhs = evaluate(lhs, env, trace)?;
+            let rhs = evaluate(rhs, env, trace)?;
 
-            let result = lhs + rhs;
-            dbg!(lhs, rhs, lhs + rhs);
-            result
+            let result = lhs.checked_add(rhs).ok_or(EvalError::Overflow)?;
+            if trace {
+                dbg!(lhs, rhs, result);
+            }
+            Ok(result)
         }
         Expr::Sub(lhs, rhs) => {
-            dbg!(expr);
-            let lhs = evaluate(lhs);
-            let rhs = evaluate(rhs);
+            if trace {
+                dbg!(expr);
+            }
+            let lhs = evaluate(lhs, env, trace)?;
+            let rhs = evaluate(rhs, env, trace)?;
 
-            let result = lhs - rhs;
-            dbg!(lhs, rhs, lhs - rhs);
-            result
+            let result = lhs.checked_sub(rhs).ok_or(EvalError::Overflow)?;
+            if trace {
+                dbg!(lhs, rhs, result);
+            }
+            Ok(result)
+        }
+        Expr::Mul(lhs, rhs) => {
+            if trace {
+                dbg!(expr);
+            }
+            let lhs = evaluate(lhs, env, trace)?;
+            let rhs = evaluate(rhs, env, trace)?;
+
+            let result = lhs.checked_mul(rhs).ok_or(EvalError::Overflow)?;
+            if trace {
+                dbg!(lhs, rhs, result);
+            }
+            Ok(result)
+        }
+        Expr::Div(lhs, rhs) => {
+            if trace {
+                dbg!(expr);
+            }
+            let lhs = evaluate(lhs, env, trace)?;
+            let rhs = evaluate(rhs, env, trace)?;
+
+            if rhs == 0 {
+                return Err(EvalError::DivisionByZero);
+            }
+            let result = lhs.checked_div(rhs).ok_or(EvalError::Overflow)?;
+            if trace {
+                dbg!(lhs, rhs, result);
+            }
+            Ok(result)
+        }
+        Expr::Number(number) => Ok(*number),
+        Expr::Var(name) => env
+            .get(name)
+            .copied()
+            .ok_or_else(|| EvalError::UndefinedVariable(name.clone())),
+    }
+}
+
+// runs one parsed line against the environment, binding `let`s as it goes.
+fn execute(stmt: &Stmt, env: &mut Environment, trace: bool) -> Result<i32, EvalError> {
+    match stmt {
+        Stmt::Let(name, expr) => {
+            let value = evaluate(expr, env, trace)?;
+            env.insert(name.clone(), value);
+            Ok(value)
+        }
+        Stmt::Expr(expr) => evaluate(expr, env, trace),
+    }
+}
+
+#[derive(Error, Debug)]
+enum EvalError {
+    #[error("undefined variable {0:?}")]
+    UndefinedVariable(String),
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("arithmetic overflow")]
+    Overflow,
+}
+
+// the span a `ParseError` blames for the failure, used to draw the caret line.
+fn error_span(err: &ParseError) -> usize {
+    match err {
+        ParseError::Expected { span, .. } => *span,
+        ParseError::UnexpectedEndOfInput { span } => *span,
+        ParseError::InvalidToken(_, span) => *span,
+        ParseError::TrailingToken { span, .. } => *span,
+        ParseError::NumberOutOfRange { span, .. } => *span,
+        ParseError::NumberLiteralOverflow { span, .. } => *span,
+    }
+}
+
+fn print_parse_error(input: &str, err: &ParseError) {
+    println!("error: {err}");
+    println!("{input}");
+    println!("{}^", " ".repeat(error_span(err)));
+}
+
+// parses and evaluates one line, printing its result or error to stdout
+// (under `--trace` the result is `dbg!`-ed to stderr instead).
+fn run_line(input: &str, env: &mut Environment, trace: bool) {
+    if trace {
+        dbg!(&input);
+    }
+
+    match parse(input) {
+        Ok(ref stmt) => {
+            if trace {
+                dbg!(stmt);
+            }
+            match execute(stmt, env, trace) {
+                Ok(result) => {
+                    if trace {
+                        dbg!(result);
+                    } else {
+                        println!("{result}");
+                    }
+                }
+                Err(err) => println!("error: {err}"),
+            }
+        }
+        Err(err) => {
+            // `collect_parse_errors` only understands the plain expression
+            // grammar and knows nothing about the `let` statement form, so
+            // for a `let`-prefixed line it doesn't just come up empty - it
+            // reinterprets the tokens as a bare expression (e.g. misreading
+            // `let x = + 2` as a trailing `x` after a one-token expression)
+            // and reports a misleading error. There's only ever one `let`
+            // statement to parse anyway, so skip recovery entirely and
+            // report `parse`'s own error for it.
+            let is_let = tokenize(input).map(|t| is_let_start(&t)).unwrap_or(false);
+            if is_let {
+                print_parse_error(input, &err);
+                return;
+            }
+
+            let errors = collect_parse_errors(input);
+            if errors.is_empty() {
+                print_parse_error(input, &err);
+            } else {
+                for err in errors {
+                    print_parse_error(input, &err);
+                }
+            }
+        }
+    }
+}
+
+fn run_repl(trace: bool) {
+    let mut editor = rustyline::DefaultEditor::new().expect("failed to start line editor");
+    let mut env = Environment::new();
+
+    loop {
+        match editor.readline("> ") {
+            Ok(line) => {
+                let _ = editor.add_history_entry(line.as_str());
+                run_line(&line, &mut env, trace);
+            }
+            Err(rustyline::error::ReadlineError::Interrupted)
+            | Err(rustyline::error::ReadlineError::Eof) => break,
+            Err(err) => {
+                println!("error: {err}");
+                break;
+            }
         }
-        Expr::Number(number) => *number,
     }
 }
 
 fn main() {
     // get all chars after the program name
-    let input = std::env::args().skip(1).collect::<Vec<String>>().join(" ");
-    dbg!(&input);
+    let mut args = std::env::args().skip(1).collect::<Vec<String>>();
 
-    match parse(&input) {
-        Ok(ref tree) => {
-            dbg!(tree);
-            let result = evaluate(tree);
-            dbg!(result);
+    let trace = match args.iter().position(|arg| arg == "--trace") {
+        Some(pos) => {
+            args.remove(pos);
+            true
         }
-        Err(err) => println!("error: {:?}", err),
+        None => false,
+    };
+
+    if args.is_empty() {
+        run_repl(trace);
+        return;
     }
+
+    let input = args.join(" ");
+    let mut env = Environment::new();
+    run_line(&input, &mut env, trace);
 }
 
-// broken: (123 + 213) - 456 + 123
\ No newline at end of file
+// now left-associative and precedence-aware: (123 + 213) - 456 + 123 * 2